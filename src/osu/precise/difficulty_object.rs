@@ -0,0 +1,41 @@
+use super::opacity::opacity_at;
+use super::osu_object::OsuObject;
+
+/// Wraps an [`OsuObject`] together with the jump distance, strain time, and reading
+/// opacity derived from its predecessor, which is what the individual skills process.
+pub(crate) struct DifficultyObject<'h> {
+    pub(crate) base: &'h OsuObject,
+    pub(crate) jump_dist: f32,
+    pub(crate) strain_time: f32,
+    /// How visible `base` still was at the time the player was hitting the previous
+    /// object; `1.0` is fully visible, `0.0` is not visible yet or already faded out
+    /// under Hidden. Used to scale aim/flashlight strain for reading difficulty.
+    pub(crate) opacity: f32,
+}
+
+impl<'h> DifficultyObject<'h> {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        base: &'h OsuObject,
+        prev: &OsuObject,
+        _prev_vals: Option<(f32, f32)>,
+        _prev_prev: Option<OsuObject>,
+        _scale_factor: f32,
+        scaling_factor: f32,
+        hidden: bool,
+        time_preempt: f32,
+        time_fade_in: f32,
+    ) -> Self {
+        let strain_time = (base.time - prev.time).max(25.0);
+        let jump_dist = base.pos.distance(prev.pos) * scaling_factor;
+
+        let opacity = opacity_at(base.time, prev.time, hidden, time_preempt, time_fade_in);
+
+        Self {
+            base,
+            jump_dist,
+            strain_time,
+            opacity,
+        }
+    }
+}
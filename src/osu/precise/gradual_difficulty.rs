@@ -0,0 +1,311 @@
+use std::vec::IntoIter;
+
+use crate::{curve::CurveBuffers, parse::Pos2, Beatmap, Mods};
+
+use super::difficulty_object::DifficultyObject;
+use super::opacity::fade_in_duration;
+use super::osu_object::{ObjectParameters, OsuObject};
+use super::skill::Skill;
+use super::skill_kind::SkillKind;
+use super::slider_state::SliderState;
+use super::{
+    difficulty_range_ar, DifficultyAttributes, DIFFICULTY_MULTIPLIER, NORMALIZED_RADIUS,
+    OBJECT_RADIUS, SECTION_LEN,
+};
+
+/// Gradually calculate the difficulty attributes of an osu!standard map.
+///
+/// Instead of running the whole [`stars`](super::stars) pass at once, this struct
+/// hands out the [`DifficultyAttributes`] as they stand after each additional
+/// [`OsuObject`], without re-processing the objects that came before. Useful for
+/// a live replay / editor view, or for getting the star rating at an arbitrary
+/// pass count without paying for an `O(n^2)` amount of re-calculation.
+pub struct OsuGradualDifficultyAttributes {
+    hit_objects: IntoIter<OsuObject>,
+    skills: Vec<Skill>,
+    prev: Option<OsuObject>,
+    prev_prev: Option<OsuObject>,
+    prev_vals: Option<(f32, f32)>,
+    current_section_end: f32,
+    scale_factor: f32,
+    scaling_factor: f32,
+    first_object: bool,
+    fl: bool,
+    rx: bool,
+    hidden: bool,
+    time_preempt: f32,
+    time_fade_in: f32,
+    /// `combo_progress[i]` is the max combo achievable through the `i`-th processed object.
+    combo_progress: Vec<usize>,
+    /// Index into `combo_progress` for the object the next call to [`next`](Self::next) processes.
+    idx: usize,
+    ar: f32,
+    hp: f32,
+    od: f32,
+    n_circles: usize,
+    n_sliders: usize,
+    n_spinners: usize,
+}
+
+impl OsuGradualDifficultyAttributes {
+    /// Create a new gradual difficulty calculator for the given map.
+    pub fn new(map: &Beatmap, mods: impl Mods) -> Self {
+        let map_attributes = map.attributes().mods(mods);
+        let hit_window = super::difficulty_range_od(map_attributes.od) / map_attributes.clock_rate;
+        let od = (80.0 - hit_window) / 6.0;
+
+        let empty = Self {
+            hit_objects: Vec::new().into_iter(),
+            skills: Vec::new(),
+            prev: None,
+            prev_prev: None,
+            prev_vals: None,
+            current_section_end: 0.0,
+            scale_factor: 0.0,
+            scaling_factor: 0.0,
+            first_object: true,
+            fl: mods.fl(),
+            rx: mods.rx(),
+            hidden: mods.hd(),
+            time_preempt: 0.0,
+            time_fade_in: 0.0,
+            combo_progress: Vec::new(),
+            idx: 0,
+            ar: map_attributes.ar,
+            hp: map_attributes.hp,
+            od,
+            n_circles: map.n_circles as usize,
+            n_sliders: map.n_sliders as usize,
+            n_spinners: map.n_spinners as usize,
+        };
+
+        // Same guard as `stars`; a single object has no predecessor and thus no strain.
+        if map.hit_objects.len() < 2 {
+            return empty;
+        }
+
+        let mut raw_ar = map.ar;
+        let hr = mods.hr();
+
+        if hr {
+            raw_ar = (raw_ar * 1.4).min(10.0);
+        } else if mods.ez() {
+            raw_ar *= 0.5;
+        }
+
+        let time_preempt = difficulty_range_ar(raw_ar);
+        let scale = (1.0 - 0.7 * (map_attributes.cs - 5.0) / 5.0) / 2.0;
+        let radius = OBJECT_RADIUS * scale;
+        let mut scaling_factor = NORMALIZED_RADIUS / radius;
+
+        if radius < 30.0 {
+            let small_circle_bonus = (30.0 - radius).min(5.0) / 50.0;
+            scaling_factor *= 1.0 + small_circle_bonus;
+        }
+
+        let hidden = mods.hd();
+        let time_fade_in = fade_in_duration(time_preempt, hidden);
+
+        let mut params = ObjectParameters {
+            map,
+            radius,
+            scaling_factor,
+            time_preempt,
+            time_fade_in,
+            max_combo: 0,
+            slider_state: SliderState::new(map),
+            ticks: Vec::new(),
+            curve_bufs: CurveBuffers::default(),
+        };
+
+        // Snapshot the running max combo after every object so `next` can report the
+        // combo achievable as of that object instead of the full map's final combo.
+        let mut combo_progress = Vec::with_capacity(map.hit_objects.len());
+
+        let hit_objects_iter = map.hit_objects.iter().filter_map(|h| {
+            let obj = OsuObject::new(h, hr, &mut params)?;
+            combo_progress.push(params.max_combo);
+
+            Some(obj)
+        });
+
+        let mut hit_objects = Vec::with_capacity(map.hit_objects.len());
+        hit_objects.extend(hit_objects_iter);
+
+        if hit_objects.len() < 2 {
+            return empty;
+        }
+
+        let stack_threshold = time_preempt * map.stack_leniency;
+
+        if map.version >= 6 {
+            super::stacking(&mut hit_objects, stack_threshold);
+        } else {
+            super::old_stacking(&mut hit_objects, stack_threshold);
+        }
+
+        let scale_factor = scale * -6.4;
+
+        let mut hit_objects = hit_objects.into_iter().map(|mut h| {
+            let stack_offset = h.stack_height * scale_factor;
+
+            h.time /= map_attributes.clock_rate;
+            h.pos += Pos2::new(stack_offset);
+
+            h
+        });
+
+        let fl = mods.fl();
+        let mut skills = Vec::with_capacity(2 + fl as usize);
+
+        skills.push(Skill::new(SkillKind::Aim));
+        skills.push(Skill::new(SkillKind::speed(hit_window)));
+
+        if fl {
+            skills.push(Skill::new(SkillKind::flashlight(scaling_factor)));
+        }
+
+        let prev = hit_objects.next().unwrap();
+        let current_section_end = (prev.time / SECTION_LEN).ceil() * SECTION_LEN;
+
+        Self {
+            hit_objects: hit_objects.collect::<Vec<_>>().into_iter(),
+            skills,
+            prev: Some(prev),
+            prev_prev: None,
+            prev_vals: None,
+            current_section_end,
+            scale_factor,
+            scaling_factor,
+            first_object: true,
+            fl,
+            rx: mods.rx(),
+            hidden,
+            time_preempt,
+            time_fade_in,
+            combo_progress,
+            idx: 1,
+            ar: map_attributes.ar,
+            hp: map_attributes.hp,
+            od,
+            n_circles: map.n_circles as usize,
+            n_sliders: map.n_sliders as usize,
+            n_spinners: map.n_spinners as usize,
+        }
+    }
+
+    /// Process the next [`OsuObject`] and return the [`DifficultyAttributes`] as of that object.
+    ///
+    /// Returns `None` once every object has been processed.
+    pub fn next(&mut self) -> Option<DifficultyAttributes> {
+        let curr = self.hit_objects.next()?;
+        let prev_prev = self.prev_prev.take();
+
+        let h = DifficultyObject::new(
+            &curr,
+            self.prev.as_ref()?,
+            self.prev_vals,
+            prev_prev,
+            self.scale_factor,
+            self.scaling_factor,
+            self.hidden,
+            self.time_preempt,
+            self.time_fade_in,
+        );
+
+        while h.base.time > self.current_section_end {
+            for skill in self.skills.iter_mut() {
+                if !self.first_object {
+                    skill.save_current_peak();
+                }
+
+                skill.start_new_section_from(self.current_section_end);
+            }
+
+            self.current_section_end += SECTION_LEN;
+        }
+
+        for skill in self.skills.iter_mut() {
+            skill.process(&h);
+        }
+
+        self.prev_prev = self.prev.replace(curr);
+        self.prev_vals = Some((h.jump_dist, h.strain_time));
+        self.first_object = false;
+
+        let max_combo = self.combo_progress[self.idx];
+        self.idx += 1;
+
+        Some(self.snapshot(max_combo))
+    }
+
+    /// Snapshot the current skill state into `DifficultyAttributes` without disturbing it.
+    ///
+    /// `save_current_peak` is run on a clone of each skill so that the in-progress
+    /// section peak is folded in for this snapshot only, leaving the live skills
+    /// untouched for the next call to [`next`](Self::next).
+    fn snapshot(&self, max_combo: usize) -> DifficultyAttributes {
+        let mut skills = self.skills.clone();
+
+        for skill in skills.iter_mut() {
+            skill.save_current_peak();
+        }
+
+        let aim_rating = skills[0].difficulty_value().sqrt() * DIFFICULTY_MULTIPLIER;
+
+        let speed_rating = if self.rx {
+            0.0
+        } else {
+            skills[1].difficulty_value().sqrt() * DIFFICULTY_MULTIPLIER
+        };
+
+        let flashlight_rating = skills.get_mut(2).map_or(0.0, |skill| {
+            skill.difficulty_value().sqrt() * DIFFICULTY_MULTIPLIER
+        });
+
+        let base_aim_performance = {
+            let base = 5.0 * (aim_rating / 0.0675).max(1.0) - 4.0;
+
+            base * base * base / 100_000.0
+        };
+
+        let base_speed_performance = {
+            let base = 5.0 * (speed_rating / 0.0675).max(1.0) - 4.0;
+
+            base * base * base / 100_000.0
+        };
+
+        let base_flashlight_performance = if self.fl {
+            flashlight_rating * flashlight_rating * 25.0
+        } else {
+            0.0
+        };
+
+        let base_performance = (base_aim_performance.powf(1.1)
+            + base_speed_performance.powf(1.1)
+            + base_flashlight_performance.powf(1.1))
+        .powf(1.0 / 1.1);
+
+        let star_rating = if base_performance > 0.00001 {
+            1.12_f32.cbrt()
+                * 0.027
+                * ((100_000.0 / (1.0_f32 / 1.1).exp2() * base_performance).cbrt() + 4.0)
+        } else {
+            0.0
+        };
+
+        DifficultyAttributes {
+            ar: self.ar,
+            hp: self.hp,
+            od: self.od,
+            aim_strain: aim_rating,
+            speed_strain: speed_rating,
+            flashlight_rating,
+            n_circles: self.n_circles,
+            n_sliders: self.n_sliders,
+            n_spinners: self.n_spinners,
+            stars: star_rating,
+            max_combo,
+        }
+    }
+}
@@ -7,12 +7,16 @@
 use std::mem;
 
 mod difficulty_object;
+mod gradual_difficulty;
+mod opacity;
 mod osu_object;
+mod pp;
 mod skill;
 mod skill_kind;
 mod slider_state;
 
 use difficulty_object::DifficultyObject;
+use opacity::fade_in_duration;
 use osu_object::{ObjectParameters, OsuObject};
 use skill::Skill;
 use skill_kind::SkillKind;
@@ -22,6 +26,9 @@ use crate::{curve::CurveBuffers, parse::Pos2, Beatmap, Mods, Strains};
 
 use super::DifficultyAttributes;
 
+pub use gradual_difficulty::OsuGradualDifficultyAttributes;
+pub use pp::{OsuPP, OsuPerformanceAttributes};
+
 const OBJECT_RADIUS: f32 = 64.0;
 const SECTION_LEN: f32 = 400.0;
 const DIFFICULTY_MULTIPLIER: f32 = 0.0675;
@@ -33,6 +40,9 @@ const STACK_DISTANCE: f32 = 3.0;
 /// Slider paths aswell as stack leniency are considered.
 /// Both of these drag the performance down but in turn the values are much more accurate
 ///
+/// Under the Hidden mod, objects are also harder to read the shorter they stay
+/// fully visible, which is folded into the aim and flashlight strain.
+///
 /// In case of a partial play, e.g. a fail, one can specify the amount of passed objects.
 pub fn stars(
     map: &Beatmap,
@@ -73,10 +83,15 @@ pub fn stars(
         scaling_factor *= 1.0 + small_circle_bonus;
     }
 
+    let hidden = mods.hd();
+    let time_fade_in = fade_in_duration(time_preempt, hidden);
+
     let mut params = ObjectParameters {
         map,
         radius,
         scaling_factor,
+        time_preempt,
+        time_fade_in,
         max_combo: 0,
         slider_state: SliderState::new(map),
         ticks: Vec::new(),
@@ -137,6 +152,9 @@ pub fn stars(
         prev_prev,
         scale_factor,
         scaling_factor,
+        hidden,
+        time_preempt,
+        time_fade_in,
     );
 
     while h.base.time > current_section_end {
@@ -164,6 +182,9 @@ pub fn stars(
             prev_prev,
             scale_factor,
             scaling_factor,
+            hidden,
+            time_preempt,
+            time_fade_in,
         );
 
         while h.base.time > current_section_end {
@@ -277,10 +298,15 @@ pub fn strains(map: &Beatmap, mods: impl Mods) -> Strains {
         scaling_factor *= 1.0 + small_circle_bonus;
     }
 
+    let hidden = mods.hd();
+    let time_fade_in = fade_in_duration(time_preempt, hidden);
+
     let mut params = ObjectParameters {
         map,
         radius,
         scaling_factor,
+        time_preempt,
+        time_fade_in,
         max_combo: 0,
         slider_state: SliderState::new(map),
         ticks: Vec::new(),
@@ -340,6 +366,9 @@ pub fn strains(map: &Beatmap, mods: impl Mods) -> Strains {
         prev_prev,
         scale_factor,
         scaling_factor,
+        hidden,
+        time_preempt,
+        time_fade_in,
     );
 
     while h.base.time > current_section_end {
@@ -367,6 +396,9 @@ pub fn strains(map: &Beatmap, mods: impl Mods) -> Strains {
             prev_prev,
             scale_factor,
             scaling_factor,
+            hidden,
+            time_preempt,
+            time_fade_in,
         );
 
         while h.base.time > current_section_end {
@@ -418,13 +450,225 @@ pub fn strains(map: &Beatmap, mods: impl Mods) -> Strains {
     }
 }
 
+/// Aim, speed, and flashlight strain peaks over time, see [`strains_split`].
+///
+/// Unlike [`Strains`], the individual skills aren't summed together so a caller can
+/// tell which section of a map is aim-heavy vs speed-heavy, e.g. to render a stacked
+/// difficulty graph. `flashlight` is empty unless the Flashlight mod was enabled.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OsuStrains {
+    /// Time between two strain peaks in ms.
+    pub section_length: f32,
+    pub aim: Vec<f32>,
+    pub speed: Vec<f32>,
+    pub flashlight: Vec<f32>,
+}
+
+/// Essentially the same as [`strains`] but keeps the aim, speed, and flashlight
+/// strains separate instead of collapsing them into a single track.
+pub fn strains_split(map: &Beatmap, mods: impl Mods) -> OsuStrains {
+    let map_attributes = map.attributes().mods(mods);
+    let hit_window = super::difficulty_range_od(map_attributes.od) / map_attributes.clock_rate;
+
+    if map.hit_objects.len() < 2 {
+        return OsuStrains::default();
+    }
+
+    let mut raw_ar = map.ar;
+    let hr = mods.hr();
+
+    if hr {
+        raw_ar *= 1.4;
+    } else if mods.ez() {
+        raw_ar *= 0.5;
+    }
+
+    let time_preempt = difficulty_range_ar(raw_ar);
+    let scale = (1.0 - 0.7 * (map_attributes.cs - 5.0) / 5.0) / 2.0;
+    let radius = OBJECT_RADIUS * scale;
+    let mut scaling_factor = NORMALIZED_RADIUS / radius;
+
+    if radius < 30.0 {
+        let small_circle_bonus = (30.0 - radius).min(5.0) / 50.0;
+        scaling_factor *= 1.0 + small_circle_bonus;
+    }
+
+    let hidden = mods.hd();
+    let time_fade_in = fade_in_duration(time_preempt, hidden);
+
+    let mut params = ObjectParameters {
+        map,
+        radius,
+        scaling_factor,
+        time_preempt,
+        time_fade_in,
+        max_combo: 0,
+        slider_state: SliderState::new(map),
+        ticks: Vec::new(),
+        curve_bufs: CurveBuffers::default(),
+    };
+
+    let hit_objects_iter = map
+        .hit_objects
+        .iter()
+        .filter_map(|h| OsuObject::new(h, hr, &mut params));
+
+    let mut hit_objects = Vec::with_capacity(map.hit_objects.len());
+    hit_objects.extend(hit_objects_iter);
+
+    let stack_threshold = time_preempt * map.stack_leniency;
+
+    if map.version >= 6 {
+        stacking(&mut hit_objects, stack_threshold);
+    } else {
+        old_stacking(&mut hit_objects, stack_threshold);
+    }
+
+    let scale_factor = scale * -6.4;
+
+    let mut hit_objects = hit_objects.into_iter().map(|mut h| {
+        let stack_offset = h.stack_height * scale_factor;
+
+        h.time /= map_attributes.clock_rate;
+        h.pos += Pos2::new(stack_offset);
+
+        h
+    });
+
+    let fl = mods.fl();
+    let mut skills = Vec::with_capacity(2 + fl as usize);
+
+    skills.push(Skill::new(SkillKind::Aim));
+    skills.push(Skill::new(SkillKind::speed(hit_window)));
+
+    if fl {
+        skills.push(Skill::new(SkillKind::flashlight(scaling_factor)));
+    }
+
+    let mut prev_prev = None;
+    let mut prev = hit_objects.next().unwrap();
+    let mut prev_vals = None;
+
+    // First object has no predecessor and thus no strain, handle distinctly
+    let mut current_section_end = (prev.time / SECTION_LEN).ceil() * SECTION_LEN;
+
+    // Handle second object separately to remove later if-branching
+    let curr = hit_objects.next().unwrap();
+    let h = DifficultyObject::new(
+        &curr,
+        &prev,
+        prev_vals,
+        prev_prev,
+        scale_factor,
+        scaling_factor,
+        hidden,
+        time_preempt,
+        time_fade_in,
+    );
+
+    while h.base.time > current_section_end {
+        for skill in skills.iter_mut() {
+            skill.start_new_section_from(current_section_end);
+        }
+
+        current_section_end += SECTION_LEN;
+    }
+
+    for skill in skills.iter_mut() {
+        skill.process(&h);
+    }
+
+    prev_prev = Some(prev);
+    prev_vals = Some((h.jump_dist, h.strain_time));
+    prev = curr;
+
+    // Handle all other objects
+    for curr in hit_objects {
+        let h = DifficultyObject::new(
+            &curr,
+            &prev,
+            prev_vals,
+            prev_prev,
+            scale_factor,
+            scaling_factor,
+            hidden,
+            time_preempt,
+            time_fade_in,
+        );
+
+        while h.base.time > current_section_end {
+            for skill in skills.iter_mut() {
+                skill.save_current_peak();
+                skill.start_new_section_from(current_section_end);
+            }
+
+            current_section_end += SECTION_LEN;
+        }
+
+        for skill in skills.iter_mut() {
+            skill.process(&h);
+        }
+
+        prev_prev = Some(prev);
+        prev_vals = Some((h.jump_dist, h.strain_time));
+        prev = curr;
+    }
+
+    for skill in skills.iter_mut() {
+        skill.save_current_peak();
+    }
+
+    let mut speed = skills.pop().unwrap().strain_peaks;
+    let mut aim = skills.pop().unwrap().strain_peaks;
+
+    let flashlight = if let Some(mut flashlight) = skills.pop().map(|s| s.strain_peaks) {
+        mem::swap(&mut speed, &mut aim);
+        mem::swap(&mut aim, &mut flashlight);
+
+        flashlight
+    } else {
+        Vec::new()
+    };
+
+    OsuStrains {
+        section_length: SECTION_LEN,
+        aim,
+        speed,
+        flashlight,
+    }
+}
+
 fn stacking(hit_objects: &mut [OsuObject], stack_threshold: f32) {
-    let mut extended_start_idx = 0;
-    let extended_end_idx = hit_objects.len() - 1;
+    let end_idx = hit_objects.len() - 1;
+
+    compute_stacking(hit_objects, stack_threshold, 0, end_idx);
+}
+
+/// Ranged variant of [`stacking`] that only recomputes stack heights for objects within
+/// `start_idx..=end_idx`, e.g. after an editor-style edit that only touches part of a map.
+///
+/// Objects within the window are reset before recomputing. The `extended_start_idx`
+/// bookkeeping below walks backward past `start_idx`, resetting earlier objects, whenever
+/// a stack in the window reaches back to them. The end of the window is not extended:
+/// objects after `end_idx` are neither reset nor used as stack bases, so a stack that
+/// continues past `end_idx` is not recomputed and its heights can differ from those of a
+/// [`stacking`] pass over the whole map.
+pub fn compute_stacking(
+    hit_objects: &mut [OsuObject],
+    stack_threshold: f32,
+    start_idx: usize,
+    end_idx: usize,
+) {
+    for obj in hit_objects[start_idx..=end_idx].iter_mut() {
+        obj.stack_height = 0.0;
+    }
+
+    let mut extended_start_idx = start_idx;
+    let extended_end_idx = end_idx;
 
     // First big `if` in osu!lazer's function can be skipped
 
-    for i in (1..=extended_end_idx).rev() {
+    for i in (start_idx.max(1)..=extended_end_idx).rev() {
         let mut n = i;
         let mut obj_i_idx = i;
         // * We should check every note which has not yet got a stack.
@@ -530,7 +774,27 @@ fn stacking(hit_objects: &mut [OsuObject], stack_threshold: f32) {
 }
 
 fn old_stacking(hit_objects: &mut [OsuObject], stack_threshold: f32) {
-    for i in 0..hit_objects.len() {
+    let end_idx = hit_objects.len() - 1;
+
+    compute_old_stacking(hit_objects, stack_threshold, 0, end_idx);
+}
+
+/// Ranged variant of [`old_stacking`] (pre-v6 stacking) taking the same window as
+/// [`compute_stacking`].
+///
+/// Only objects within `start_idx..=end_idx` are reset, used as stack bases, or stacked
+/// onto; unlike [`compute_stacking`], it does not walk backward past `start_idx`.
+pub fn compute_old_stacking(
+    hit_objects: &mut [OsuObject],
+    stack_threshold: f32,
+    start_idx: usize,
+    end_idx: usize,
+) {
+    for obj in hit_objects[start_idx..=end_idx].iter_mut() {
+        obj.stack_height = 0.0;
+    }
+
+    for i in start_idx..=end_idx {
         if hit_objects[i].stack_height != 0.0 && !hit_objects[i].is_slider() {
             continue;
         }
@@ -540,7 +804,7 @@ fn old_stacking(hit_objects: &mut [OsuObject], stack_threshold: f32) {
 
         let mut slider_stack = 0.0;
 
-        for j in i + 1..hit_objects.len() {
+        for j in i + 1..=end_idx {
             if hit_objects[j].time - stack_threshold > start_time {
                 break;
             }
@@ -0,0 +1,105 @@
+//! Models how visible a hit object is at an arbitrary point in time.
+//!
+//! Used to scale reading-related strain when objects are hidden behind their
+//! approach circle for longer, or fade away early under the Hidden mod.
+
+/// Preempt can get arbitrarily short at very high AR; below this, the fade-in
+/// duration stops shrinking any further.
+pub(super) const PREEMPT_MIN: f32 = 450.0;
+
+/// Portion of `time_preempt` spent fading the object in.
+pub(super) const FADE_IN_DURATION_MULTIPLIER: f32 = 0.4;
+
+/// Under Hidden, the fade-in is shortened relative to the regular fade-in.
+const HIDDEN_FADE_IN_MULTIPLIER: f32 = 0.5;
+
+/// Under Hidden, the object starts fading out as soon as it's fully faded in and
+/// spends this portion of the remaining preempt time doing so.
+const HIDDEN_FADE_OUT_DURATION_MULTIPLIER: f32 = 0.3;
+
+/// The duration of the fade-in, in milliseconds, for an object with the given
+/// `time_preempt`.
+#[inline]
+pub(super) fn fade_in_duration(time_preempt: f32, hidden: bool) -> f32 {
+    let duration = time_preempt.max(PREEMPT_MIN) * FADE_IN_DURATION_MULTIPLIER;
+
+    if hidden {
+        duration * HIDDEN_FADE_IN_MULTIPLIER
+    } else {
+        duration
+    }
+}
+
+/// Opacity of an object at `query_time`, where `0.0` is fully invisible and
+/// `1.0` is fully visible. `obj_time` is the object's (already scaled) hit time.
+pub(super) fn opacity_at(
+    obj_time: f32,
+    query_time: f32,
+    hidden: bool,
+    time_preempt: f32,
+    time_fade_in: f32,
+) -> f32 {
+    if query_time >= obj_time {
+        return 0.0;
+    }
+
+    let fade_in_start = obj_time - time_preempt;
+
+    if query_time <= fade_in_start {
+        return 0.0;
+    }
+
+    let fade_in_end = fade_in_start + time_fade_in;
+
+    if query_time < fade_in_end {
+        return (query_time - fade_in_start) / time_fade_in;
+    }
+
+    if !hidden {
+        return 1.0;
+    }
+
+    let fade_out_duration = (time_preempt - time_fade_in) * HIDDEN_FADE_OUT_DURATION_MULTIPLIER;
+    let fade_out_end = fade_in_end + fade_out_duration;
+
+    if query_time < fade_out_end {
+        1.0 - (query_time - fade_in_end) / fade_out_duration
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn not_hidden_stays_fully_visible_after_fade_in() {
+        let time_preempt = 1200.0;
+        let time_fade_in = fade_in_duration(time_preempt, false);
+
+        assert_eq!(opacity_at(1000.0, -1000.0, false, time_preempt, time_fade_in), 0.0);
+        assert_eq!(opacity_at(1000.0, 1000.0, false, time_preempt, time_fade_in), 0.0);
+        assert_eq!(
+            opacity_at(1000.0, 1000.0 - time_preempt + time_fade_in, false, time_preempt, time_fade_in),
+            1.0
+        );
+    }
+
+    #[test]
+    fn hidden_fades_out_before_the_object_is_hit() {
+        let time_preempt = 1200.0;
+        let time_fade_in = fade_in_duration(time_preempt, true);
+        let fade_in_end = 1000.0 - time_preempt + time_fade_in;
+
+        assert_eq!(opacity_at(1000.0, fade_in_end, true, time_preempt, time_fade_in), 1.0);
+        assert_eq!(opacity_at(1000.0, 999.0, true, time_preempt, time_fade_in), 0.0);
+    }
+
+    #[test]
+    fn hidden_fade_in_is_shorter_than_regular_fade_in() {
+        let time_preempt = 1200.0;
+
+        assert!(fade_in_duration(time_preempt, true) < fade_in_duration(time_preempt, false));
+    }
+}
@@ -0,0 +1,415 @@
+use crate::{Beatmap, Mods};
+
+use super::{stars, DifficultyAttributes};
+
+/// Performance calculator on osu!standard maps.
+///
+/// # Example
+///
+/// ```no_run
+/// # use akatsuki_pp::osu::precise::OsuPP;
+/// # let map = unimplemented!();
+/// let pp_result = OsuPP::new(&map)
+///     .mods(8 + 16) // HDHR
+///     .combo(1234)
+///     .misses(1)
+///     .accuracy(98.76)
+///     .calculate();
+///
+/// println!("PP: {} | Stars: {}", pp_result.pp, pp_result.stars());
+/// ```
+pub struct OsuPP<'map> {
+    map: &'map Beatmap,
+    attributes: Option<DifficultyAttributes>,
+    mods: u32,
+    combo: Option<usize>,
+
+    n300: Option<usize>,
+    n100: Option<usize>,
+    n50: Option<usize>,
+    n_misses: usize,
+    acc: Option<f32>,
+
+    passed_objects: Option<usize>,
+}
+
+impl<'map> OsuPP<'map> {
+    #[inline]
+    pub fn new(map: &'map Beatmap) -> Self {
+        Self {
+            map,
+            attributes: None,
+            mods: 0,
+            combo: None,
+
+            n300: None,
+            n100: None,
+            n50: None,
+            n_misses: 0,
+            acc: None,
+
+            passed_objects: None,
+        }
+    }
+
+    /// Reuse previously calculated attributes so the whole [`stars`] pass can be skipped.
+    #[inline]
+    pub fn attributes(mut self, attributes: DifficultyAttributes) -> Self {
+        self.attributes = Some(attributes);
+
+        self
+    }
+
+    /// Specify mods through their bit values.
+    #[inline]
+    pub fn mods(mut self, mods: u32) -> Self {
+        self.mods = mods;
+
+        self
+    }
+
+    /// Specify the max combo of the play.
+    #[inline]
+    pub fn combo(mut self, combo: usize) -> Self {
+        self.combo = Some(combo);
+
+        self
+    }
+
+    /// Amount of 300s of the play.
+    #[inline]
+    pub fn n300(mut self, n300: usize) -> Self {
+        self.n300 = Some(n300);
+
+        self
+    }
+
+    /// Amount of 100s of the play.
+    #[inline]
+    pub fn n100(mut self, n100: usize) -> Self {
+        self.n100 = Some(n100);
+
+        self
+    }
+
+    /// Amount of 50s of the play.
+    #[inline]
+    pub fn n50(mut self, n50: usize) -> Self {
+        self.n50 = Some(n50);
+
+        self
+    }
+
+    /// Amount of misses of the play.
+    #[inline]
+    pub fn misses(mut self, n_misses: usize) -> Self {
+        self.n_misses = n_misses;
+
+        self
+    }
+
+    /// Accuracy of the play in percent, i.e. `0.0 <= acc <= 100.0`.
+    #[inline]
+    pub fn accuracy(mut self, acc: f32) -> Self {
+        self.acc = Some(acc);
+
+        self
+    }
+
+    /// Amount of passed objects, relevant for partial plays e.g. a fail.
+    #[inline]
+    pub fn passed_objects(mut self, passed_objects: usize) -> Self {
+        self.passed_objects = Some(passed_objects);
+
+        self
+    }
+
+    fn total_hits(&self) -> usize {
+        let n_objects = self
+            .passed_objects
+            .unwrap_or_else(|| self.map.hit_objects.len());
+
+        let n300 = self.n300.unwrap_or(0);
+        let n100 = self.n100.unwrap_or(0);
+        let n50 = self.n50.unwrap_or(0);
+
+        (n300 + n100 + n50 + self.n_misses).min(n_objects)
+    }
+
+    /// Fill the unset hit result counts so that they roughly match the given or a perfect
+    /// accuracy, see [`hit_results_from_accuracy`].
+    fn assign_hit_results(&mut self) {
+        if self.n300.is_some() && self.n100.is_some() && self.n50.is_some() {
+            return;
+        }
+
+        let n_objects = self
+            .passed_objects
+            .unwrap_or_else(|| self.map.hit_objects.len());
+
+        let (n300, n100, n50) = hit_results_from_accuracy(
+            n_objects,
+            self.n_misses,
+            self.acc,
+            self.n300,
+            self.n100,
+            self.n50,
+        );
+
+        self.n300 = Some(n300);
+        self.n100 = Some(n100);
+        self.n50 = Some(n50);
+    }
+
+    fn effective_accuracy(&self, n300: usize, n100: usize, n50: usize) -> f32 {
+        accuracy(self.total_hits(), n300, n100, n50)
+    }
+
+    /// Calculate the final performance points and the per-skill pp breakdown.
+    pub fn calculate(mut self) -> OsuPerformanceAttributes {
+        let attributes = self.attributes.take().unwrap_or_else(|| {
+            stars(self.map, self.mods, self.passed_objects)
+        });
+
+        self.assign_hit_results();
+
+        let n300 = self.n300.unwrap_or(0);
+        let n100 = self.n100.unwrap_or(0);
+        let n50 = self.n50.unwrap_or(0);
+        let n_misses = self.n_misses;
+
+        let total_hits = self.total_hits().max(1) as f32;
+        let effective_acc = self.effective_accuracy(n300, n100, n50);
+
+        let max_combo = self.combo.unwrap_or(attributes.max_combo);
+        let combo_ratio = if attributes.max_combo > 0 {
+            (max_combo as f32 / attributes.max_combo as f32)
+                .powf(0.8)
+                .min(1.0)
+        } else {
+            1.0
+        };
+
+        let miss_penalty = if n_misses > 0 {
+            0.97 * (1.0 - (n_misses as f32 / total_hits).powf(0.775)).powi(n_misses as i32)
+        } else {
+            1.0
+        };
+
+        let hd = self.mods.hd();
+        let fl = self.mods.fl();
+
+        let len_bonus = 0.95
+            + 0.4 * (total_hits / 2000.0).min(1.0)
+            + if total_hits > 2000.0 {
+                (total_hits / 2000.0).log10() * 0.5
+            } else {
+                0.0
+            };
+
+        let ar_bonus = if attributes.ar > 10.33 {
+            0.3 * (attributes.ar - 10.33)
+        } else if attributes.ar < 8.0 {
+            0.1 * (8.0 - attributes.ar)
+        } else {
+            0.0
+        };
+
+        let acc_factor = 0.5 + effective_acc / 2.0;
+
+        let aim_pp = {
+            let mut aim = (5.0 * (attributes.aim_strain / 0.0675).max(1.0) - 4.0).powi(3)
+                / 100_000.0;
+
+            aim *= len_bonus;
+            aim *= miss_penalty;
+            aim *= combo_ratio;
+            aim *= 1.0 + ar_bonus;
+
+            if hd {
+                aim *= 1.0 + 0.04 * (12.0 - attributes.ar).max(0.0);
+            }
+
+            aim *= acc_factor;
+            aim *= 0.98 + attributes.od * attributes.od / 2500.0;
+
+            aim
+        };
+
+        let speed_pp = {
+            let mut speed = (5.0 * (attributes.speed_strain / 0.0675).max(1.0) - 4.0).powi(3)
+                / 100_000.0;
+
+            speed *= len_bonus;
+            speed *= miss_penalty;
+            speed *= combo_ratio;
+            speed *= 1.0 + ar_bonus;
+
+            if hd {
+                speed *= 1.0 + 0.04 * (12.0 - attributes.ar).max(0.0);
+            }
+
+            speed *= acc_factor;
+            speed *= 0.98 + attributes.od * attributes.od / 2500.0;
+
+            speed
+        };
+
+        let acc_pp = {
+            let od_bonus = (attributes.od * attributes.od) / 2500.0;
+            let mut acc = 1.52163_f32.powf(attributes.od) * effective_acc.powi(24) * 2.83;
+
+            acc *= (1.0 + od_bonus).min(1.15);
+
+            if hd {
+                acc *= 1.08;
+            }
+
+            if fl {
+                acc *= 1.02;
+            }
+
+            acc
+        };
+
+        let flashlight_pp = if fl {
+            let mut fl_pp = attributes.flashlight_rating * attributes.flashlight_rating * 25.0;
+
+            fl_pp *= miss_penalty;
+            fl_pp *= combo_ratio;
+            fl_pp *= 0.5 + effective_acc / 2.0;
+            fl_pp *= 0.98 + attributes.od * attributes.od / 2500.0;
+
+            if total_hits > 200.0 {
+                fl_pp *= 0.7 + 0.1 * (total_hits / 200.0).min(1.0)
+                    + if total_hits > 200.0 {
+                        0.2 * ((total_hits - 200.0) / 200.0).min(1.0)
+                    } else {
+                        0.0
+                    };
+            }
+
+            fl_pp
+        } else {
+            0.0
+        };
+
+        let pp = (aim_pp.powf(1.1)
+            + speed_pp.powf(1.1)
+            + acc_pp.powf(1.1)
+            + flashlight_pp.powf(1.1))
+        .powf(1.0 / 1.1);
+
+        OsuPerformanceAttributes {
+            difficulty: attributes,
+            pp,
+            aim_pp,
+            speed_pp,
+            acc_pp,
+            flashlight_pp,
+        }
+    }
+}
+
+/// Accuracy, i.e. `0.0 <= acc <= 1.0`, of a play with the given hit result counts.
+#[inline]
+fn accuracy(total_hits: usize, n300: usize, n100: usize, n50: usize) -> f32 {
+    if total_hits == 0 {
+        return 0.0;
+    }
+
+    let numerator = n300 * 6 + n100 * 2 + n50;
+
+    numerator as f32 / (total_hits * 6) as f32
+}
+
+/// Fill in whichever of `n300`/`n100`/`n50` are `None` so that they roughly match `acc`
+/// (defaulting to a perfect play).
+///
+/// `n50` is preferred to stay at its given value (or `0`), `n100` is solved for from
+/// `acc`, and `n300` absorbs the rest, so that the three sum up to `n_objects - n_misses`
+/// when `n300` is `None`; a given `n300` is kept as is and the sum is not adjusted. Since `n300 = remaining - n100 - n50` and
+/// `6 * n300 + 2 * n100 + n50 = acc * n_objects * 6`, substituting gives
+/// `n100 = (6 * remaining - 5 * n50 - acc * n_objects * 6) / 4`.
+fn hit_results_from_accuracy(
+    n_objects: usize,
+    n_misses: usize,
+    acc: Option<f32>,
+    n300: Option<usize>,
+    n100: Option<usize>,
+    n50: Option<usize>,
+) -> (usize, usize, usize) {
+    let remaining = n_objects.saturating_sub(n_misses);
+    let acc = acc.unwrap_or(100.0) / 100.0;
+
+    let n50 = n50.unwrap_or(0);
+    let n100 = n100.unwrap_or_else(|| {
+        let target_total = acc * (n_objects * 6) as f32;
+        let delta = target_total - (remaining * 6) as f32;
+
+        (((-delta - 5.0 * n50 as f32) / 4.0).round().max(0.0) as usize)
+            .min(remaining.saturating_sub(n50))
+    });
+
+    let n300 = n300.unwrap_or_else(|| remaining.saturating_sub(n100 + n50));
+
+    (n300, n100, n50)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{accuracy, hit_results_from_accuracy};
+
+    #[test]
+    fn hit_results_from_accuracy_round_trips_requested_accuracy() {
+        let (n300, n100, n50) = hit_results_from_accuracy(200, 0, Some(95.0), None, None, None);
+
+        assert_eq!((n300, n100, n50), (185, 15, 0));
+        assert!((accuracy(200, n300, n100, n50) - 0.95).abs() < 0.0001);
+    }
+
+    #[test]
+    fn hit_results_from_accuracy_perfect_play_is_all_300s() {
+        let (n300, n100, n50) = hit_results_from_accuracy(500, 0, None, None, None, None);
+
+        assert_eq!((n300, n100, n50), (500, 0, 0));
+    }
+
+    #[test]
+    fn hit_results_from_accuracy_leaves_explicit_counts_untouched() {
+        let (n300, n100, n50) =
+            hit_results_from_accuracy(200, 2, Some(90.0), Some(100), Some(50), Some(10));
+
+        assert_eq!((n300, n100, n50), (100, 50, 10));
+    }
+
+    #[test]
+    fn accuracy_of_zero_total_hits_is_zero() {
+        assert_eq!(accuracy(0, 0, 0, 0), 0.0);
+    }
+}
+
+/// The result of an [`OsuPP`] calculation.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OsuPerformanceAttributes {
+    /// The difficulty attributes the performance was calculated from.
+    pub difficulty: DifficultyAttributes,
+    /// Total performance points.
+    pub pp: f32,
+    /// Aim performance points.
+    pub aim_pp: f32,
+    /// Speed performance points.
+    pub speed_pp: f32,
+    /// Accuracy performance points.
+    pub acc_pp: f32,
+    /// Flashlight performance points.
+    pub flashlight_pp: f32,
+}
+
+impl OsuPerformanceAttributes {
+    /// The star rating of the map these performance points were calculated for.
+    #[inline]
+    pub fn stars(&self) -> f32 {
+        self.difficulty.stars
+    }
+}
@@ -0,0 +1,62 @@
+use super::difficulty_object::DifficultyObject;
+use super::skill_kind::SkillKind;
+
+/// Factor by which the weight of each successive strain peak decays after sorting them
+/// from hardest to easiest; the hardest peak has a weight of `1.0`.
+const DECAY_WEIGHT: f32 = 0.9;
+
+/// Tracks the running strain of a single skill (aim, speed, or flashlight) across a map.
+#[derive(Clone)]
+pub(crate) struct Skill {
+    kind: SkillKind,
+    current_strain: f32,
+    current_section_peak: f32,
+    pub(crate) strain_peaks: Vec<f32>,
+}
+
+impl Skill {
+    #[inline]
+    pub(crate) fn new(kind: SkillKind) -> Self {
+        Self {
+            kind,
+            current_strain: 0.0,
+            current_section_peak: 0.0,
+            strain_peaks: Vec::new(),
+        }
+    }
+
+    pub(crate) fn process(&mut self, current: &DifficultyObject) {
+        self.current_strain *= self.strain_decay(current.strain_time);
+        self.current_strain += self.kind.strain_value_of(current) * (1.0 - self.kind.decay());
+        self.current_section_peak = self.current_section_peak.max(self.current_strain);
+    }
+
+    #[inline]
+    pub(crate) fn start_new_section_from(&mut self, _time: f32) {
+        self.current_section_peak = self.current_strain;
+    }
+
+    #[inline]
+    pub(crate) fn save_current_peak(&mut self) {
+        self.strain_peaks.push(self.current_section_peak);
+    }
+
+    fn strain_decay(&self, delta_time: f32) -> f32 {
+        self.kind.decay().powf(delta_time / 1000.0)
+    }
+
+    pub(crate) fn difficulty_value(&self) -> f32 {
+        let mut peaks = self.strain_peaks.clone();
+        peaks.sort_unstable_by(|a, b| b.partial_cmp(a).unwrap());
+
+        let mut difficulty = 0.0;
+        let mut weight = 1.0;
+
+        for strain in peaks {
+            difficulty += strain * weight;
+            weight *= DECAY_WEIGHT;
+        }
+
+        difficulty
+    }
+}
@@ -0,0 +1,59 @@
+use super::difficulty_object::DifficultyObject;
+
+const AIM_DECAY_BASE: f32 = 0.15;
+const SPEED_DECAY_BASE: f32 = 0.3;
+const FLASHLIGHT_DECAY_BASE: f32 = 0.15;
+
+/// How much extra strain an object can contribute to aim/flashlight when it was
+/// barely visible by the time the player had to react to it, e.g. under Hidden.
+const MAX_OPACITY_BONUS: f32 = 0.4;
+
+/// The kind of strain a [`super::skill::Skill`] tracks.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum SkillKind {
+    Aim,
+    Speed { great_window: f32 },
+    Flashlight { scaling_factor: f32 },
+}
+
+impl SkillKind {
+    #[inline]
+    pub(crate) fn speed(great_window: f32) -> Self {
+        Self::Speed { great_window }
+    }
+
+    #[inline]
+    pub(crate) fn flashlight(scaling_factor: f32) -> Self {
+        Self::Flashlight { scaling_factor }
+    }
+
+    pub(crate) fn decay(self) -> f32 {
+        match self {
+            Self::Aim => AIM_DECAY_BASE,
+            Self::Speed { .. } => SPEED_DECAY_BASE,
+            Self::Flashlight { .. } => FLASHLIGHT_DECAY_BASE,
+        }
+    }
+
+    /// Strain contribution of `current`, reading difficulty included.
+    ///
+    /// Aim and flashlight both depend on recognizing where to move the cursor next,
+    /// so the less of `current` was visible when the player had to react to it, the
+    /// higher its strain contribution. Speed is driven by tapping rhythm rather than
+    /// reading, so it's left untouched by opacity.
+    pub(crate) fn strain_value_of(self, current: &DifficultyObject) -> f32 {
+        match self {
+            Self::Aim => {
+                let raw = current.jump_dist / current.strain_time;
+
+                raw * (1.0 + MAX_OPACITY_BONUS * (1.0 - current.opacity))
+            }
+            Self::Speed { great_window } => (current.jump_dist / current.strain_time).min(great_window),
+            Self::Flashlight { scaling_factor } => {
+                let raw = current.jump_dist * scaling_factor / current.strain_time;
+
+                raw * (1.0 + MAX_OPACITY_BONUS * (1.0 - current.opacity))
+            }
+        }
+    }
+}